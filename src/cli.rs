@@ -0,0 +1,134 @@
+use crate::primitives::Registry;
+use crate::scene_file::{self, SceneFile};
+use crate::stats::Stats;
+use crate::{lowpoly, obj, stl, IndexedMesh};
+use std::env;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+const USAGE: &str = "usage:
+    svg3d
+    svg3d info <model.obj>
+    svg3d convert [options] <model.obj> <out.obj|out.stl>
+    svg3d render <scene.svg3d> <out.svg>
+    svg3d render --shape <shape> <out.svg>
+    svg3d upgrade <scene.svg3d>
+    svg3d shapes
+
+convert options, applied in this order:
+    --weld <tolerance>    merge vertices within tolerance of each other
+    --lowpoly <cell size> collapse the mesh onto a grid of cells
+    --jitter <amount>     move every vertex by up to amount
+    --seed <number>       seed for --jitter, 0 by default";
+
+// runs the svg3d command line with the shapes of `registry`, so that programs
+// registering their own shapes can offer them from the same commands
+pub fn run(registry: &Registry) {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        [] => render_shape(registry, "octahedron(scale=15)", "octahedron.svg"),
+        ["info", path] => info(path),
+        ["convert", options @ .., input, output] => convert(options, input, output),
+        ["render", "--shape", shape, output] => render_shape(registry, shape, output),
+        ["render", path, output] => render_scene(registry, path, output),
+        ["upgrade", path] => upgrade(path),
+        ["shapes"] => println!("{}", registry.names().join("\n")),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2)
+        }
+    }
+}
+
+fn number<T: FromStr>(option: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| exit_with(&format!("invalid value for {}: {}", option, value)))
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn load(path: &str) -> IndexedMesh {
+    obj::load(path).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)))
+}
+
+fn info(path: &str) {
+    println!("{}", Stats::new(&load(path)))
+}
+
+fn convert(options: &[&str], input: &str, output: &str) {
+    let (mut weld, mut cell_size, mut jitter, mut seed) = (None, None, None, 0);
+
+    for option in options.chunks(2) {
+        match option {
+            ["--weld", value] => weld = Some(number(option[0], value)),
+            ["--lowpoly", value] => cell_size = Some(number(option[0], value)),
+            ["--jitter", value] => jitter = Some(number(option[0], value)),
+            ["--seed", value] => seed = number(option[0], value),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2)
+            }
+        }
+    }
+
+    let mut mesh = load(input);
+
+    if let Some(tolerance) = weld {
+        mesh = mesh.weld(tolerance);
+    }
+
+    if let Some(cell_size) = cell_size {
+        mesh = lowpoly::cluster(&mesh, cell_size)
+            .unwrap_or_else(|e| exit_with(&format!("--lowpoly: {}", e)));
+    }
+
+    if let Some(amount) = jitter {
+        mesh = lowpoly::jitter(&mesh, amount, seed)
+            .unwrap_or_else(|e| exit_with(&format!("--jitter: {}", e)));
+    }
+
+    let saved = if output.ends_with(".stl") {
+        stl::save(output, &mesh)
+    } else if output.ends_with(".obj") {
+        obj::save(output, &mesh)
+    } else {
+        exit_with(&format!("{}: expected an .obj or .stl file name", output))
+    };
+
+    if let Err(e) = saved {
+        exit_with(&format!("{}: {}", output, e))
+    }
+}
+
+fn render_shape(registry: &Registry, shape: &str, output: &str) {
+    SceneFile::with_shape(registry, shape)
+        .unwrap_or_else(|e| exit_with(&e))
+        .render(output.to_string())
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", output, e)))
+}
+
+fn render_scene(registry: &Registry, path: &str, output: &str) {
+    scene_file::load(path, registry)
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)))
+        .render(output.to_string())
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", output, e)))
+}
+
+// prints the scene file at `path` migrated to the current schema version
+fn upgrade(path: &str) {
+    let upgraded = fs::read_to_string(path)
+        .and_then(|source| scene_file::upgrade(&source))
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+    print!("{}", upgraded)
+}
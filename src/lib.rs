@@ -0,0 +1,1352 @@
+use nalgebra::{Affine2, Isometry3, Matrix4, Perspective3, Point3, Vector2, Vector3};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+use svg::node::element::path::Data;
+use svg::node::element::{ClipPath, Definitions, Group, Path, Polygon, Style};
+use svg::node::{Node, Value};
+use svg::Document;
+
+pub mod cli;
+pub mod lowpoly;
+pub mod obj;
+pub mod primitives;
+pub mod scene_file;
+pub mod stats;
+pub mod stl;
+
+pub use primitives::{cube, icosahedron, octahedron};
+
+pub type StyleMap<V> = HashMap<String, V>;
+pub type Face = [Point3<f32>; 3];
+pub type Shader<T> = Arc<dyn Fn(&FaceInfo) -> StyleMap<T> + Send + Sync>;
+
+// a mesh that shares vertices between faces, as loaded from model files
+#[derive(Clone, Debug, Default)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Point3<f32>>,
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    // merges the corners of a triangle soup that lie within `tolerance` of
+    // each other (0.0 merges only identical points), dropping the faces that
+    // collapse in the process. each corner joins the first vertex close enough
+    // to it, so chains of points can end up further apart than `tolerance`
+    pub fn from_faces(faces: &[Face], tolerance: f32) -> Self {
+        let mut mesh = IndexedMesh::default();
+        let mut exact: HashMap<[u32; 3], usize> = HashMap::new();
+        // the vertices in each tolerance-sized cell. a point within tolerance
+        // of another is at most one cell away from it
+        let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+
+        for face in faces {
+            let mut group = [0; 3];
+
+            for (corner, point) in group.iter_mut().zip(face.iter()) {
+                let vertices = &mut mesh.vertices;
+
+                if tolerance > 0.0 {
+                    let cell = [
+                        (point.x / tolerance).floor() as i64,
+                        (point.y / tolerance).floor() as i64,
+                        (point.z / tolerance).floor() as i64,
+                    ];
+
+                    let mut nearby = vec![];
+                    for dx in -1..=1 {
+                        for dy in -1..=1 {
+                            for dz in -1..=1 {
+                                let neighbour = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                                if let Some(indices) = cells.get(&neighbour) {
+                                    nearby.extend(indices.iter().copied().filter(|&index| {
+                                        (vertices[index] - point).norm() <= tolerance
+                                    }));
+                                }
+                            }
+                        }
+                    }
+
+                    *corner = match nearby.into_iter().min() {
+                        Some(index) => index,
+                        None => {
+                            vertices.push(*point);
+                            cells.entry(cell).or_default().push(vertices.len() - 1);
+                            vertices.len() - 1
+                        }
+                    };
+                } else {
+                    let key = [point.x.to_bits(), point.y.to_bits(), point.z.to_bits()];
+                    *corner = *exact.entry(key).or_insert_with(|| {
+                        vertices.push(*point);
+                        vertices.len() - 1
+                    });
+                }
+            }
+
+            let [a, b, c] = group;
+            if a != b && b != c && c != a {
+                mesh.indices.push(group);
+            }
+        }
+
+        mesh
+    }
+
+    pub fn weld(&self, tolerance: f32) -> Self {
+        IndexedMesh::from_faces(&self.faces(), tolerance)
+    }
+
+    pub fn faces(&self) -> Vec<Face> {
+        self.indices
+            .iter()
+            .map(|group| {
+                [
+                    self.vertices[group[0]],
+                    self.vertices[group[1]],
+                    self.vertices[group[2]],
+                ]
+            })
+            .collect()
+    }
+}
+
+// what a shader knows about the face it is styling
+pub struct FaceInfo {
+    // position of the face in the mesh's faces
+    pub index: usize,
+    pub winding: f32,
+    // in world units
+    pub area: f32,
+    // after projection and viewport mapping, in viewbox units, see
+    // Mesh::projected_areas
+    pub projected_area: f32,
+}
+
+pub fn area(face: &Face) -> f32 {
+    let [p1, p2, p3] = face;
+    (p2 - p1).cross(&(p3 - p1)).norm() / 2.0
+}
+
+fn winding(face: &Face) -> f32 {
+    let [p1, p2, p3] = face;
+    (p2 - p1).cross(&(p3 - p1))[2]
+}
+
+const DEFAULT_STROKE_WIDTH: f32 = 0.005;
+
+type Attributes = Vec<(String, Value)>;
+
+fn styled<N: Node>(mut node: N, style: &[(String, Value)]) -> N {
+    for (name, value) in style {
+        node.assign(name.as_str(), value.clone());
+    }
+    node
+}
+
+// the distinct fill and stroke colors of a document
+#[derive(Default)]
+struct Palette {
+    fills: Vec<String>,
+    strokes: Vec<String>,
+}
+
+impl Palette {
+    fn variable(&mut self, property: &str, color: &str) -> String {
+        let colors = if property == "fill" {
+            &mut self.fills
+        } else {
+            &mut self.strokes
+        };
+
+        let index = match colors.iter().position(|known| known == color) {
+            Some(index) => index,
+            None => {
+                colors.push(color.to_string());
+                colors.len() - 1
+            }
+        };
+
+        format!("var(--svg3d-{}-{}, {})", property, index, color)
+    }
+
+    fn sort(&mut self) {
+        self.fills.sort_unstable();
+        self.strokes.sort_unstable();
+    }
+
+    fn css(&self) -> String {
+        let fills = self.fills.iter().enumerate().map(|(i, c)| ("fill", i, c));
+        let strokes = self
+            .strokes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| ("stroke", i, c));
+
+        let declarations = fills
+            .chain(strokes)
+            .map(|(property, index, color)| format!("--svg3d-{}-{}: {};", property, index, color))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        format!(":root {{ {} }}", declarations)
+    }
+}
+
+// moves fill and stroke colors into a `style` attribute as css variables.
+// var() is only reliably supported in css, not in presentation attributes
+fn themed(palette: Option<&mut Palette>, style: Vec<(String, Value)>) -> Vec<(String, Value)> {
+    let palette = match palette {
+        Some(palette) => palette,
+        None => return style,
+    };
+
+    let (colors, mut rest): (Vec<_>, Vec<_>) = style
+        .into_iter()
+        .partition(|(name, value)| (name == "fill" || name == "stroke") && &**value != "none");
+
+    if !colors.is_empty() {
+        let declarations = colors
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, palette.variable(name, value)))
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        rest.push(("style".to_string(), Value::from(declarations)));
+    }
+
+    rest
+}
+
+// there is no first-class points method, PR this maybe?
+fn points(face: &Face) -> String {
+    face.iter()
+        .map(|point| [point.x.to_string(), point.y.to_string()].join(","))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn add_face(group: Group, face: &Face, corner_radius: f32, style: &[(String, Value)]) -> Group {
+    if corner_radius > 0.0 {
+        let path = Path::new().set("d", rounded_corners(face, corner_radius));
+        group.add(styled(path, style))
+    } else {
+        let polygon = Polygon::new().set("points", points(face));
+
+        group.add(styled(polygon, style))
+    }
+}
+
+// replaces each corner with a circular arc tangent to both of its edges.
+// the tangent points are kept within half of each edge so that the arcs
+// of neighbouring corners never cross
+fn rounded_corners(points: &[Point3<f32>], radius: f32) -> Data {
+    let n = points.len();
+    let mut data = Data::new();
+
+    for i in 0..n {
+        let prev = points[(i + n - 1) % n];
+        let corner = points[i];
+        let next = points[(i + 1) % n];
+
+        let to_prev = Vector2::new(prev.x - corner.x, prev.y - corner.y);
+        let to_next = Vector2::new(next.x - corner.x, next.y - corner.y);
+        let (prev_len, next_len) = (to_prev.norm(), to_next.norm());
+        let tan_half = (to_prev.angle(&to_next) / 2.0).tan();
+
+        if prev_len <= f32::EPSILON || next_len <= f32::EPSILON || tan_half <= 0.0 {
+            data = if i == 0 {
+                data.move_to((corner.x, corner.y))
+            } else {
+                data.line_to((corner.x, corner.y))
+            };
+            continue;
+        }
+
+        let tangent = (radius / tan_half).min(prev_len.min(next_len) / 2.0);
+        let arc_radius = tangent * tan_half;
+        let start = to_prev * (tangent / prev_len);
+        let end = to_next * (tangent / next_len);
+        let (start_x, start_y) = (corner.x + start.x, corner.y + start.y);
+        let (end_x, end_y) = (corner.x + end.x, corner.y + end.y);
+
+        // the arc turns the same way the outline does at this corner
+        let turn = -to_prev.x * to_next.y + to_prev.y * to_next.x;
+        let sweep = if turn > 0.0 { 1 } else { 0 };
+
+        data = if i == 0 {
+            data.move_to((start_x, start_y))
+        } else {
+            data.line_to((start_x, start_y))
+        };
+        data = data.elliptical_arc_to((arc_radius, arc_radius, 0, 0, sweep, end_x, end_y));
+    }
+
+    data.close()
+}
+
+// cheap to clone: the faces and shader are shared, not copied
+#[derive(Clone)]
+pub struct Mesh<T> {
+    pub faces: Arc<[Face]>,
+    pub style: HashMap<String, String>,
+    pub shader: Option<Shader<T>>,
+    // radius of the arc drawn at each polygon corner, in viewbox units.
+    // 0.0 keeps the sharp-cornered polygons
+    pub corner_radius: f32,
+    pub halo: Option<Halo>,
+    // faces that cover less than this much of the viewbox once projected (see
+    // projected_areas) are not drawn. 0.0 keeps every visible face
+    pub min_projected_area: f32,
+    // index of another mesh in the same scene whose projected silhouette this
+    // mesh is clipped to, e.g. to show a mechanism only within its casing
+    pub clip_to: Option<usize>,
+    pub linejoin: LineJoin,
+    // None leaves the svg default, butt
+    pub linecap: Option<LineCap>,
+    // None leaves the svg default, 4. svg requires at least 1, smaller values
+    // are drawn as 1
+    pub miter_limit: Option<f32>,
+}
+
+impl<T> Mesh<T> {
+    pub fn new<F: Into<Arc<[Face]>>>(faces: F) -> Self {
+        Mesh {
+            faces: faces.into(),
+            style: HashMap::new(),
+            shader: None,
+            corner_radius: 0.0,
+            halo: None,
+            min_projected_area: 0.0,
+            clip_to: None,
+            linejoin: LineJoin::Round,
+            linecap: None,
+            miter_limit: None,
+        }
+    }
+
+    pub fn face_area(&self, index: usize) -> f32 {
+        area(&self.faces[index])
+    }
+
+    pub fn area(&self) -> f32 {
+        self.faces.iter().map(area).sum()
+    }
+
+    // the signed area of each face once projected into `viewport`, in viewbox
+    // units, as compared with min_projected_area. for a view without a
+    // viewport of its own that's Viewport::for_document. View::transform isn't
+    // taken into account, so a scaled panel covers a different area on screen.
+    // faces turned away from the camera have a negative area
+    pub fn projected_areas(&self, camera: &Camera, viewport: &Viewport) -> Vec<f32> {
+        project(camera.projection(viewport), viewport, &self.faces)
+            .iter()
+            .map(|face| winding(face) / 2.0)
+            .collect()
+    }
+}
+
+// a casing stroke drawn underneath all of a mesh's outlines, so that its lines
+// stay legible where they pass over the busy geometry behind the mesh
+#[derive(Clone)]
+pub struct Halo {
+    pub color: String,
+    // how far the casing extends past either side of the outline, in viewbox units
+    pub width: f32,
+}
+
+impl Default for Halo {
+    fn default() -> Self {
+        Halo {
+            color: "white".to_string(),
+            width: 0.005,
+        }
+    }
+}
+
+// how stroked outlines meet at corners. shaders can set these per face
+// through their `stroke-linejoin` and `stroke-linecap` output, e.g.
+// `LineJoin::Miter.to_string()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl fmt::Display for LineJoin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        })
+    }
+}
+
+impl FromStr for LineJoin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "miter" => Ok(LineJoin::Miter),
+            "round" => Ok(LineJoin::Round),
+            "bevel" => Ok(LineJoin::Bevel),
+            _ => Err(format!("unknown line join {}", s)),
+        }
+    }
+}
+
+// how stroked outlines end, for open paths
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl fmt::Display for LineCap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        })
+    }
+}
+
+impl FromStr for LineCap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "butt" => Ok(LineCap::Butt),
+            "round" => Ok(LineCap::Round),
+            "square" => Ok(LineCap::Square),
+            _ => Err(format!("unknown line cap {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fov {
+    Degrees(f32),
+    Radians(f32),
+}
+
+impl Fov {
+    pub fn radians(self) -> f32 {
+        match self {
+            Fov::Degrees(degrees) => degrees.to_radians(),
+            Fov::Radians(radians) => radians,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CameraError {
+    FieldOfView(Fov),
+    Aspect(f32),
+    Near(f32),
+    NearNotBeforeFar { near: f32, far: f32 },
+    // `from` and `to` coincide, or `up` is parallel to the direction between them
+    Orientation,
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CameraError::FieldOfView(fov) => write!(
+                f,
+                "field of view must be between 0 and 180 degrees, got {:?} ({} degrees)",
+                fov,
+                fov.radians().to_degrees()
+            ),
+            CameraError::Aspect(aspect) => {
+                write!(f, "aspect must be positive and finite, got {}", aspect)
+            }
+            CameraError::Near(near) => write!(f, "near plane must be positive, got {}", near),
+            CameraError::NearNotBeforeFar { near, far } => write!(
+                f,
+                "near plane must be closer than far plane, got near {} and far {}",
+                near, far
+            ),
+            CameraError::Orientation => write!(
+                f,
+                "camera needs distinct from and to points and an up vector that isn't parallel to the view direction"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+#[derive(Clone)]
+pub struct Camera {
+    view: Isometry3<f32>,
+    // in radians
+    fovy: f32,
+    // None derives the aspect from the viewport the camera renders into
+    aspect: Option<f32>,
+    near: f32,
+    far: f32,
+}
+
+impl Camera {
+    pub fn new(
+        fovy: Fov,
+        aspect: Option<f32>,
+        near: f32,
+        far: f32,
+        from: Point3<f32>,
+        to: Point3<f32>,
+        up: Vector3<f32>,
+    ) -> Result<Self, CameraError> {
+        let radians = fovy.radians();
+        if !(radians > 0.0 && radians < std::f32::consts::PI) {
+            return Err(CameraError::FieldOfView(fovy));
+        }
+
+        if let Some(aspect) = aspect {
+            if !(aspect > 0.0 && aspect.is_finite()) {
+                return Err(CameraError::Aspect(aspect));
+            }
+        }
+
+        if !near.is_finite() || near <= 0.0 {
+            return Err(CameraError::Near(near));
+        }
+
+        if !far.is_finite() || far <= near {
+            return Err(CameraError::NearNotBeforeFar { near, far });
+        }
+
+        if (to - from).cross(&up).norm() <= f32::EPSILON {
+            return Err(CameraError::Orientation);
+        }
+
+        Ok(Camera {
+            view: Isometry3::look_at_rh(&from, &to, &up),
+            fovy: radians,
+            aspect,
+            near,
+            far,
+        })
+    }
+
+    fn projection(&self, viewport: &Viewport) -> Matrix4<f32> {
+        let aspect = self
+            .aspect
+            .unwrap_or_else(|| viewport.width / viewport.height);
+
+        Perspective3::new(aspect, self.fovy, self.near, self.far).to_homogeneous()
+            * self.view.to_homogeneous()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub minx: f32,
+    pub miny: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            minx: -0.5,
+            miny: -0.5,
+            width: 1.0,
+            height: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    // the viewbox of a width x height document, with its shorter side one unit
+    // long and centered on the origin. views without a viewport of their own
+    // fill it. an empty document has no aspect, so it's taken as 1 pixel wide
+    // or tall
+    pub fn for_document(width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let shorter = width.min(height) as f32;
+        let (width, height) = (width as f32 / shorter, height as f32 / shorter);
+
+        Viewport {
+            minx: -width / 2.0,
+            miny: -height / 2.0,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Scene<T> {
+    meshes: Vec<Mesh<T>>,
+}
+
+impl<T> Scene<T> {
+    pub fn new(meshes: Vec<Mesh<T>>) -> Result<Self, SceneError> {
+        for (mesh, clip_to) in meshes.iter().map(|mesh| mesh.clip_to).enumerate() {
+            if let Some(clip_to) = clip_to {
+                if clip_to >= meshes.len() {
+                    return Err(SceneError::ClipTo {
+                        mesh,
+                        clip_to,
+                        meshes: meshes.len(),
+                    });
+                }
+            }
+        }
+
+        Ok(Scene { meshes })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneError {
+    // a mesh is clipped to a mesh index past the end of the scene
+    ClipTo {
+        mesh: usize,
+        clip_to: usize,
+        meshes: usize,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::ClipTo {
+                mesh,
+                clip_to,
+                meshes,
+            } => write!(
+                f,
+                "mesh {} is clipped to mesh {}, but the scene only has {} meshes",
+                mesh, clip_to, meshes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Clone)]
+pub struct View<T> {
+    pub camera: Camera,
+    pub scene: Scene<T>,
+    // None fills the whole document
+    pub viewport: Option<Viewport>,
+    // 2d affine transform of the rendered view, in viewbox units, applied
+    // after the viewport mapping. handy for rotating or mirroring a panel.
+    // nalgebra::try_convert checks that a Matrix3 is affine
+    pub transform: Affine2<f32>,
+}
+
+impl<T> View<T> {
+    pub fn new(camera: Camera, scene: Scene<T>) -> Self {
+        View {
+            camera,
+            scene,
+            viewport: None,
+            transform: Affine2::identity(),
+        }
+    }
+}
+
+// from world space to viewbox units
+fn project(projection: Matrix4<f32>, viewport: &Viewport, faces: &[Face]) -> Vec<Face> {
+    // from xyz to xyzw
+    let with_w = faces.iter().map(|[p1, p2, p3]| {
+        [
+            p1.to_homogeneous(),
+            p2.to_homogeneous(),
+            p3.to_homogeneous(),
+        ]
+    });
+
+    let projected = with_w.map(|[p1, p2, p3]| [projection * p1, projection * p2, projection * p3]);
+
+    let points_by_w = projected.map(|[p1, p2, p3]| {
+        [
+            Point3::new(p1.x / p1.w, p1.y / p1.w, p1.z / p1.w),
+            Point3::new(p2.x / p2.w, p2.y / p2.w, p2.z / p2.w),
+            Point3::new(p3.x / p3.w, p3.y / p3.w, p3.z / p3.w),
+        ]
+    });
+
+    points_by_w
+        .map(|mut face| {
+            face.iter_mut().for_each(|point| {
+                point.x = (1.0 + point.x) * viewport.width / 2.0 + viewport.minx;
+                point.y = (1.0 - point.y) * viewport.height / 2.0 + viewport.miny;
+            });
+
+            face
+        })
+        .collect()
+}
+
+// the area covered by the projected faces, facing the camera or not
+fn silhouette(projection: Matrix4<f32>, viewport: &Viewport, faces: &[Face]) -> ClipPath {
+    project(projection, viewport, faces)
+        .iter()
+        .fold(ClipPath::new(), |clip_path, face| {
+            clip_path.add(Polygon::new().set("points", points(face)))
+        })
+}
+
+// scenes can be built on one thread and rendered on another
+#[derive(Clone)]
+pub struct Engine<T> {
+    views: Vec<View<T>>,
+    pub width: u32,
+    pub height: u32,
+    // gives every group and face an id that stays the same from one frame of
+    // a sequence to the next, and keeps hidden faces around as `display="none"`
+    // elements, so that consecutive frames only differ in their `points`, `d`,
+    // `transform` and style attributes (and in the order of the faces)
+    pub stable_ids: bool,
+    // writes fill and stroke colors as `var(--svg3d-fill-N, color)` and lists
+    // their defaults in a `:root` style block, so that the page embedding the
+    // document can re-theme it. N counts the document's distinct colors in
+    // sorted order
+    pub css_variables: bool,
+}
+
+impl<T> Engine<T>
+where
+    T: Into<Value>,
+{
+    pub fn new(views: Vec<View<T>>) -> Self {
+        Engine {
+            views,
+            width: 512,
+            height: 512,
+            stable_ids: false,
+            css_variables: false,
+        }
+    }
+
+    pub fn render(&self, filename: String) -> io::Result<()> {
+        svg::save(filename, &self.document())
+    }
+
+    pub fn document(&self) -> Document {
+        if !self.css_variables {
+            return self.draw(None);
+        }
+
+        // the colors are numbered in sorted order rather than in the order
+        // they are drawn, which changes with the camera, so that a variable
+        // names the same color in every frame. the first pass only collects them
+        let mut palette = Palette::default();
+        self.draw(Some(&mut palette));
+        palette.sort();
+        self.draw(Some(&mut palette))
+    }
+
+    fn draw(&self, mut palette: Option<&mut Palette>) -> Document {
+        // as in Viewport::for_document
+        let (width, height) = (self.width.max(1), self.height.max(1));
+        let view_box = Viewport::for_document(width, height);
+        let mut document = Document::new()
+            .set(
+                "viewBox",
+                (
+                    view_box.minx,
+                    view_box.miny,
+                    view_box.width,
+                    view_box.height,
+                ),
+            )
+            .set("width", width)
+            .set("height", height);
+
+        let mut panels = vec![];
+        let mut clip_paths: Vec<(String, ClipPath)> = vec![];
+
+        for (view_index, view) in self.views.iter().enumerate() {
+            let view_id = format!("svg3d-v{}", view_index);
+            let viewport = view.viewport.unwrap_or(view_box);
+            let projection = view.camera.projection(&viewport);
+            let groups = view
+                .scene
+                .meshes
+                .iter()
+                .enumerate()
+                .map(|(mesh_index, mesh)| {
+                    let id = format!("{}-m{}", view_id, mesh_index);
+                    let id = if self.stable_ids {
+                        Some(id.as_str())
+                    } else {
+                        None
+                    };
+                    let group =
+                        self.create_group(projection, &viewport, mesh, id, palette.as_deref_mut());
+
+                    match mesh.clip_to {
+                        Some(clip_index) => {
+                            let clip_id = format!("{}-clip{}", view_id, clip_index);
+
+                            if !clip_paths.iter().any(|(id, _)| *id == clip_id) {
+                                // in range, Scene::new checks
+                                let clip_mesh = &view.scene.meshes[clip_index];
+
+                                let clip_path = silhouette(projection, &viewport, &clip_mesh.faces)
+                                    .set("id", clip_id.as_str());
+                                clip_paths.push((clip_id.clone(), clip_path));
+                            }
+
+                            group.set("clip-path", format!("url(#{})", clip_id))
+                        }
+                        None => group,
+                    }
+                });
+
+            if view.transform == Affine2::identity() && !self.stable_ids {
+                panels.extend(groups);
+            } else {
+                let m = view.transform.matrix();
+                let mut panel = Group::new().set(
+                    "transform",
+                    format!(
+                        "matrix({} {} {} {} {} {})",
+                        m[(0, 0)],
+                        m[(1, 0)],
+                        m[(0, 1)],
+                        m[(1, 1)],
+                        m[(0, 2)],
+                        m[(1, 2)]
+                    ),
+                );
+
+                if self.stable_ids {
+                    panel = panel.set("id", view_id.as_str());
+                }
+
+                for group in groups {
+                    panel = panel.add(group);
+                }
+
+                panels.push(panel);
+            }
+        }
+
+        if let Some(palette) = palette {
+            document = document.add(Style::new(palette.css()));
+        }
+
+        if !clip_paths.is_empty() {
+            let mut definitions = Definitions::new();
+            for (_, clip_path) in clip_paths {
+                definitions = definitions.add(clip_path);
+            }
+            document = document.add(definitions);
+        }
+
+        for panel in panels {
+            document = document.add(panel);
+        }
+
+        document
+    }
+
+    fn create_group(
+        &self,
+        projection: Matrix4<f32>,
+        viewport: &Viewport,
+        mesh: &Mesh<T>,
+        id: Option<&str>,
+        mut palette: Option<&mut Palette>,
+    ) -> Group {
+        let viewport_transformed = project(projection, viewport, &mesh.faces);
+
+        let mut z_centroids = viewport_transformed
+            .into_iter()
+            .enumerate()
+            .map(|(index, face)| {
+                let z_centroid = face.iter().map(|point| point[2]).sum::<f32>() / 3.0;
+                (index, face, z_centroid)
+            })
+            .collect::<Vec<(usize, Face, f32)>>();
+
+        z_centroids
+            .sort_unstable_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut sorted_faces = z_centroids
+            .into_iter()
+            .map(|(index, face, _)| (index, face))
+            .collect::<Vec<(usize, Face)>>();
+
+        sorted_faces.reverse();
+
+        let mut group_style = vec![
+            ("fill".to_string(), Value::from("white")),
+            // ("fill-opacity".to_string(), Value::from(0.75)),
+            ("fill-opacity".to_string(), Value::from(1.0)),
+            ("stroke".to_string(), Value::from("black")),
+            (
+                "stroke-linejoin".to_string(),
+                Value::from(mesh.linejoin.to_string()),
+            ),
+            (
+                "stroke-width".to_string(),
+                Value::from(DEFAULT_STROKE_WIDTH),
+            ),
+        ];
+
+        if let Some(linecap) = mesh.linecap {
+            group_style.push((
+                "stroke-linecap".to_string(),
+                Value::from(linecap.to_string()),
+            ));
+        }
+
+        if let Some(miter_limit) = mesh.miter_limit {
+            group_style.push((
+                "stroke-miterlimit".to_string(),
+                Value::from(miter_limit.max(1.0)),
+            ));
+        }
+
+        for (name, value) in &mesh.style {
+            group_style.retain(|(default, _)| default != name);
+            group_style.push((name.clone(), Value::from(value.as_str())));
+        }
+
+        let mut group = styled(Group::new(), &themed(palette.as_deref_mut(), group_style));
+
+        if let Some(id) = id {
+            group = group.set("id", id);
+        }
+
+        let base_stroke_width = mesh
+            .style
+            .get("stroke-width")
+            .and_then(|value| value.parse::<f32>().ok())
+            .unwrap_or(DEFAULT_STROKE_WIDTH);
+
+        let hidden = |id: String| {
+            vec![
+                ("id".to_string(), Value::from(id)),
+                ("display".to_string(), Value::from("none")),
+            ]
+        };
+
+        // (face, style of its casing, style of its outline), in drawing order
+        let mut drawn: Vec<(Face, Attributes, Attributes)> = vec![];
+
+        for (index, face) in sorted_faces {
+            let winding = winding(&face);
+
+            // the winding is twice the signed area of the projected face, as in
+            // Mesh::projected_areas
+            let projected_area = winding / 2.0;
+
+            let face_id = id.map(|id| format!("{}-f{}", id, index));
+
+            if winding <= 0.0 || projected_area < mesh.min_projected_area {
+                if let Some(face_id) = face_id {
+                    drawn.push((face, hidden(format!("{}-halo", face_id)), hidden(face_id)));
+                }
+            } else {
+                let info = FaceInfo {
+                    index,
+                    winding,
+                    area: mesh.face_area(index),
+                    projected_area,
+                };
+
+                let style: Attributes = match &mesh.shader {
+                    Some(shader) => shader(&info)
+                        .into_iter()
+                        .map(|(name, value)| (name, value.into()))
+                        .collect(),
+                    None => vec![],
+                };
+
+                // the casing has to follow the outline's width and corners
+                let mut casing = style
+                    .iter()
+                    .filter(|(name, _)| {
+                        name == "stroke-linejoin"
+                            || name == "stroke-linecap"
+                            || name == "stroke-miterlimit"
+                    })
+                    .cloned()
+                    .collect::<Attributes>();
+
+                if let (Some(halo), Some(stroke_width)) = (
+                    &mesh.halo,
+                    style
+                        .iter()
+                        .find(|(name, _)| name == "stroke-width")
+                        .and_then(|(_, value)| value.parse::<f32>().ok()),
+                ) {
+                    casing.push((
+                        "stroke-width".to_string(),
+                        Value::from(stroke_width + 2.0 * halo.width),
+                    ));
+                }
+
+                let mut style = themed(palette.as_deref_mut(), style);
+
+                if let Some(face_id) = face_id {
+                    casing.push(("id".to_string(), Value::from(format!("{}-halo", face_id))));
+                    style.push(("id".to_string(), Value::from(face_id)));
+                }
+
+                drawn.push((face, casing, style));
+            }
+        }
+
+        // the casings of all of the faces go underneath all of the outlines,
+        // so that they only hide the geometry behind the mesh, not the
+        // outlines of its own neighbouring faces
+        if let Some(halo) = &mesh.halo {
+            let casing_style = vec![
+                ("fill".to_string(), Value::from("none")),
+                ("stroke".to_string(), Value::from(halo.color.as_str())),
+                (
+                    "stroke-width".to_string(),
+                    Value::from(base_stroke_width + 2.0 * halo.width),
+                ),
+            ];
+
+            let mut casings = styled(Group::new(), &themed(palette, casing_style));
+
+            if let Some(id) = id {
+                casings = casings.set("id", format!("{}-halo", id));
+            }
+
+            for (face, casing, _) in &drawn {
+                casings = add_face(casings, face, mesh.corner_radius, casing);
+            }
+
+            group = group.add(casings);
+        }
+
+        for (face, _, style) in &drawn {
+            group = add_face(group, face, mesh.corner_radius, style);
+        }
+
+        group
+    }
+}
+
+const _: fn() = || {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<Engine<String>>();
+    send_sync::<scene_file::SceneFile>();
+    send_sync::<primitives::Registry>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn camera(from: Point3<f32>) -> Camera {
+        Camera::new(
+            Fov::Degrees(15.0),
+            None,
+            10.0,
+            200.0,
+            from,
+            Point3::origin(),
+            Vector3::y(),
+        )
+        .unwrap()
+    }
+
+    fn scaled_cube(scale: f32) -> Vec<Face> {
+        cube()
+            .iter()
+            .map(|face| [scale * face[0], scale * face[1], scale * face[2]])
+            .collect()
+    }
+
+    #[test]
+    fn welds_points_on_either_side_of_a_cell_boundary() {
+        let faces = [
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.004_999_99, 1.0, 0.0),
+            ],
+            [
+                Point3::new(0.005_000_01, 1.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+            ],
+        ];
+
+        let welded = IndexedMesh::from_faces(&faces, 0.01);
+        assert_eq!(welded.vertices.len(), 4);
+        assert_eq!(welded.indices, vec![[0, 1, 2], [2, 1, 3]]);
+
+        // further apart than the tolerance, or not exactly equal
+        assert_eq!(IndexedMesh::from_faces(&faces, 1e-9).vertices.len(), 5);
+        assert_eq!(IndexedMesh::from_faces(&faces, 0.0).vertices.len(), 5);
+    }
+
+    #[test]
+    fn welding_drops_collapsed_faces() {
+        let faces = [[
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.001, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ]];
+
+        assert_eq!(IndexedMesh::from_faces(&faces, 0.01).indices.len(), 0);
+        assert_eq!(IndexedMesh::from_faces(&faces, 0.0).indices.len(), 1);
+    }
+
+    #[test]
+    fn projected_areas_of_a_closed_mesh_cancel_out() {
+        let mesh = Mesh::<String>::new(scaled_cube(15.0));
+        let areas = mesh.projected_areas(
+            &camera(Point3::new(50.0, 40.0, 120.0)),
+            &Viewport::default(),
+        );
+
+        assert_eq!(areas.len(), mesh.faces.len());
+        assert!(areas.iter().any(|area| *area > 0.0));
+        assert!(areas.iter().any(|area| *area < 0.0));
+        assert!(areas.iter().sum::<f32>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn projected_areas_match_what_the_shader_sees() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let recorder = seen.clone();
+
+        let mut mesh = Mesh::<String>::new(scaled_cube(15.0));
+        mesh.shader = Some(Arc::new(move |info: &FaceInfo| {
+            recorder
+                .lock()
+                .unwrap()
+                .push((info.index, info.projected_area));
+            StyleMap::new()
+        }));
+
+        let camera = camera(Point3::new(50.0, 40.0, 120.0));
+        let areas = mesh.projected_areas(&camera, &Viewport::for_document(800, 400));
+
+        let scene = Scene::new(vec![mesh]).unwrap();
+        let mut engine = Engine::new(vec![View::new(camera, scene)]);
+        engine.width = 800;
+        engine.height = 400;
+        engine.document();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), areas.iter().filter(|area| **area > 0.0).count());
+        for (index, area) in seen.iter() {
+            assert_eq!(*area, areas[*index]);
+        }
+    }
+
+    fn camera_error(
+        fovy: Fov,
+        aspect: Option<f32>,
+        near: f32,
+        far: f32,
+        from: Point3<f32>,
+        up: Vector3<f32>,
+    ) -> Option<CameraError> {
+        Camera::new(fovy, aspect, near, far, from, Point3::origin(), up).err()
+    }
+
+    #[test]
+    fn cameras_reject_invalid_parameters() {
+        let from = Point3::new(0.0, 0.0, 50.0);
+        let fov = Fov::Degrees(15.0);
+        let y = Vector3::y();
+
+        for &fovy in &[
+            Fov::Degrees(0.0),
+            Fov::Degrees(180.0),
+            Fov::Radians(f32::NAN),
+        ] {
+            match camera_error(fovy, None, 10.0, 200.0, from, y) {
+                Some(CameraError::FieldOfView(_)) => (),
+                _ => panic!("expected {:?} to be rejected", fovy),
+            }
+        }
+
+        for &aspect in &[0.0, -1.0, f32::INFINITY] {
+            assert_eq!(
+                camera_error(fov, Some(aspect), 10.0, 200.0, from, y),
+                Some(CameraError::Aspect(aspect))
+            );
+        }
+
+        for &near in &[0.0, -1.0] {
+            assert_eq!(
+                camera_error(fov, None, near, 200.0, from, y),
+                Some(CameraError::Near(near))
+            );
+        }
+
+        for &far in &[10.0, 5.0] {
+            assert_eq!(
+                camera_error(fov, None, 10.0, far, from, y),
+                Some(CameraError::NearNotBeforeFar { near: 10.0, far })
+            );
+        }
+
+        assert_eq!(
+            camera_error(fov, None, 10.0, 200.0, Point3::origin(), y),
+            Some(CameraError::Orientation)
+        );
+        assert_eq!(
+            camera_error(fov, None, 10.0, 200.0, from, Vector3::z()),
+            Some(CameraError::Orientation)
+        );
+
+        assert_eq!(camera_error(fov, Some(1.5), 10.0, 200.0, from, y), None);
+    }
+
+    #[test]
+    fn fields_of_view_in_degrees_and_radians_agree() {
+        let from = Point3::new(50.0, 40.0, 120.0);
+        let build = |fovy| {
+            Camera::new(
+                fovy,
+                None,
+                10.0,
+                200.0,
+                from,
+                Point3::origin(),
+                Vector3::y(),
+            )
+            .ok()
+            .unwrap()
+        };
+
+        let viewport = Viewport::for_document(640, 480);
+        assert_eq!(
+            build(Fov::Degrees(15.0)).projection(&viewport),
+            build(Fov::Radians(15f32.to_radians())).projection(&viewport)
+        );
+    }
+
+    fn frame(mesh: &Mesh<String>, from: Point3<f32>, css_variables: bool) -> String {
+        let scene = Scene::new(vec![mesh.clone()]).unwrap();
+        let mut engine = Engine::new(vec![View::new(camera(from), scene)]);
+        engine.stable_ids = true;
+        engine.css_variables = css_variables;
+        engine.document().to_string()
+    }
+
+    fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+        let start = element.find(&format!(" {}=\"", name))? + name.len() + 3;
+        let end = start + element[start..].find('"')?;
+        Some(&element[start..end])
+    }
+
+    // the elements of a document with an id, by id
+    fn elements(document: &str) -> HashMap<String, String> {
+        document
+            .lines()
+            .filter_map(|element| {
+                attribute(element, "id").map(|id| (id.to_string(), element.to_string()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn css_variables_name_the_same_colors_from_every_camera() {
+        let mut mesh = Mesh::new(scaled_cube(15.0));
+        mesh.shader = Some(Arc::new(|info: &FaceInfo| {
+            let mut style = StyleMap::new();
+            let fill = if info.index < 6 { "#f00" } else { "#00f" };
+            style.insert("fill".to_string(), fill.to_string());
+            style
+        }));
+
+        let frames = [
+            frame(&mesh, Point3::new(50.0, 40.0, 120.0), true),
+            frame(&mesh, Point3::new(120.0, 40.0, 50.0), true),
+        ];
+
+        for frame in &frames {
+            assert!(frame.contains(
+                ":root { --svg3d-fill-0: #00f; --svg3d-fill-1: #f00; \
+                 --svg3d-fill-2: white; --svg3d-stroke-0: black; }"
+            ));
+        }
+
+        let (first, second) = (elements(&frames[0]), elements(&frames[1]));
+        let mut compared = 0;
+        for (id, element) in &first {
+            let style = attribute(element, "style");
+            if let (Some(style), Some(other)) = (style, second.get(id)) {
+                if id.contains("-f") && attribute(other, "display").is_none() {
+                    assert_eq!(Some(style), attribute(other, "style"), "{}", id);
+                    compared += 1;
+                }
+            }
+        }
+        assert!(compared > 0);
+    }
+
+    #[test]
+    fn stable_ids_are_the_same_from_every_camera() {
+        let mut mesh = Mesh::new(scaled_cube(15.0));
+        mesh.halo = Some(Halo::default());
+
+        let frames = [
+            elements(&frame(&mesh, Point3::new(50.0, 40.0, 120.0), false)),
+            elements(&frame(&mesh, Point3::new(-50.0, -40.0, -120.0), false)),
+        ];
+
+        let ids = |elements: &HashMap<String, String>| {
+            let mut ids = elements.keys().cloned().collect::<Vec<String>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&frames[0]), ids(&frames[1]));
+
+        for elements in &frames {
+            for index in 0..mesh.faces.len() {
+                for id in &[
+                    format!("svg3d-v0-m0-f{}", index),
+                    format!("svg3d-v0-m0-f{}-halo", index),
+                ] {
+                    assert!(elements.contains_key(id), "no element {}", id);
+                }
+            }
+            assert!(elements.contains_key("svg3d-v0-m0-halo"));
+
+            // the faces turned away from this camera, and their casings
+            let hidden = elements
+                .values()
+                .filter(|element| attribute(element, "display") == Some("none"))
+                .count();
+            assert!(hidden > 0 && hidden < 2 * mesh.faces.len());
+            assert_eq!(hidden % 2, 0);
+        }
+    }
+
+    #[test]
+    fn view_transforms_are_affine() {
+        use nalgebra::Matrix3;
+
+        let projective = Matrix3::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.0, 1.0);
+        assert!(nalgebra::try_convert::<_, Affine2<f32>>(projective).is_none());
+
+        let mirror = Matrix3::new(-1.0, 0.0, 0.25, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0);
+        let scene = Scene::new(vec![Mesh::<String>::new(scaled_cube(15.0))]).unwrap();
+        let mut view = View::new(camera(Point3::new(50.0, 40.0, 120.0)), scene);
+        view.transform = nalgebra::try_convert(mirror).unwrap();
+
+        let document = Engine::new(vec![view]).document().to_string();
+        assert!(document.contains("transform=\"matrix(-1 0 0 1 0.25 0)\""));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::IndexedMesh;
+use nalgebra::{Point3, Vector3};
+use std::collections::{HashMap, HashSet};
+
+// deliberately coarse versions of smooth meshes, for the faceted look that
+// flat-shaded svg does well. unlike decimation this doesn't try to preserve
+// the shape, only to keep it recognisable
+
+// snaps the mesh onto a grid of `cell_size` cells: all of the vertices in a
+// cell merge into one at their average position, and the faces that collapse
+// or end up on top of another face are dropped
+pub fn cluster(mesh: &IndexedMesh, cell_size: f32) -> Result<IndexedMesh, String> {
+    if !cell_size.is_finite() || cell_size <= 0.0 {
+        return Err(format!(
+            "cell size must be positive and finite, got {}",
+            cell_size
+        ));
+    }
+
+    let mut cells: HashMap<[i64; 3], usize> = HashMap::new();
+    let mut sums: Vec<(Vector3<f32>, f32)> = vec![];
+
+    let remap = mesh
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let key = [
+                (vertex.x / cell_size).floor() as i64,
+                (vertex.y / cell_size).floor() as i64,
+                (vertex.z / cell_size).floor() as i64,
+            ];
+
+            let index = *cells.entry(key).or_insert_with(|| {
+                sums.push((Vector3::zeros(), 0.0));
+                sums.len() - 1
+            });
+
+            sums[index].0 += vertex.coords;
+            sums[index].1 += 1.0;
+            index
+        })
+        .collect::<Vec<usize>>();
+
+    let mut seen = HashSet::new();
+    let indices = mesh
+        .indices
+        .iter()
+        .map(|[a, b, c]| [remap[*a], remap[*b], remap[*c]])
+        .filter(|&[a, b, c]| {
+            let mut key = [a, b, c];
+            key.sort_unstable();
+            a != b && b != c && c != a && seen.insert(key)
+        })
+        .collect();
+
+    Ok(IndexedMesh {
+        vertices: sums
+            .into_iter()
+            .map(|(sum, count)| Point3::from(sum / count))
+            .collect(),
+        indices,
+    })
+}
+
+// moves every vertex by up to `amount` along each axis. shared vertices move
+// together, so the mesh doesn't crack, and the same seed always gives the same
+// result
+pub fn jitter(mesh: &IndexedMesh, amount: f32, seed: u64) -> Result<IndexedMesh, String> {
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(format!(
+            "jitter amount must be finite and not negative, got {}",
+            amount
+        ));
+    }
+
+    let mut state = seed;
+    let mut offset = || (2.0 * unit(splitmix64(&mut state)) - 1.0) * amount;
+
+    Ok(IndexedMesh {
+        vertices: mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex + Vector3::new(offset(), offset(), offset()))
+            .collect(),
+        indices: mesh.indices.clone(),
+    })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// the top 24 bits as a float in [0, 1)
+fn unit(bits: u64) -> f32 {
+    (bits >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::icosphere;
+
+    fn sphere() -> IndexedMesh {
+        IndexedMesh::from_faces(&icosphere(2), 0.0)
+    }
+
+    #[test]
+    fn cluster_drops_collapsed_and_duplicate_faces() {
+        let mesh = IndexedMesh {
+            vertices: vec![
+                Point3::new(0.1, 0.1, 0.1),
+                Point3::new(0.2, 0.1, 0.1),
+                Point3::new(1.5, 0.1, 0.1),
+                Point3::new(0.1, 1.5, 0.1),
+                Point3::new(1.6, 0.1, 0.1),
+            ],
+            indices: vec![
+                // 0 and 1 share a cell
+                [0, 1, 2],
+                [0, 2, 3],
+                // the same face as the one above once 2 and 4 merge
+                [3, 1, 4],
+            ],
+        };
+
+        let clustered = cluster(&mesh, 1.0).unwrap();
+        assert_eq!(clustered.vertices.len(), 3);
+        assert_eq!(clustered.indices, vec![[0, 1, 2]]);
+        assert_eq!(clustered.vertices[0], Point3::new(0.15, 0.1, 0.1));
+    }
+
+    #[test]
+    fn cluster_rejects_unusable_cell_sizes() {
+        for &cell_size in &[0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(cluster(&sphere(), cell_size).is_err());
+        }
+    }
+
+    #[test]
+    fn jitter_is_repeatable_and_bounded() {
+        let mesh = sphere();
+        let jittered = jitter(&mesh, 0.05, 7).unwrap();
+
+        assert_eq!(jittered.vertices, jitter(&mesh, 0.05, 7).unwrap().vertices);
+        assert_ne!(jittered.vertices, jitter(&mesh, 0.05, 8).unwrap().vertices);
+
+        for (before, after) in mesh.vertices.iter().zip(&jittered.vertices) {
+            assert!((after - before).amax() <= 0.05);
+        }
+    }
+
+    #[test]
+    fn jitter_keeps_shared_vertices_shared() {
+        let mesh = sphere();
+        let jittered = jitter(&mesh, 0.05, 7).unwrap();
+
+        assert_eq!(jittered.indices, mesh.indices);
+        let welded = IndexedMesh::from_faces(&jittered.faces(), 0.0);
+        assert_eq!(welded.vertices.len(), mesh.vertices.len());
+    }
+
+    #[test]
+    fn jitter_rejects_unusable_amounts() {
+        for &amount in &[-1.0, f32::NAN, f32::INFINITY] {
+            assert!(jitter(&sphere(), amount, 0).is_err());
+        }
+        assert_eq!(
+            jitter(&sphere(), 0.0, 0).unwrap().vertices,
+            sphere().vertices
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::IndexedMesh;
+use nalgebra::Point3;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<IndexedMesh> {
+    parse(&fs::read_to_string(path)?)
+}
+
+pub fn save<P: AsRef<Path>>(path: P, mesh: &IndexedMesh) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, mesh)?;
+    writer.flush()
+}
+
+pub fn write<W: Write>(writer: &mut W, mesh: &IndexedMesh) -> io::Result<()> {
+    for vertex in &mesh.vertices {
+        writeln!(writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+    }
+
+    for [a, b, c] in &mesh.indices {
+        writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
+    }
+
+    Ok(())
+}
+
+// reads the vertex positions and faces of a wavefront obj file, ignoring
+// everything else (normals, texture coordinates, groups, materials).
+// polygons with more than three corners are split into triangle fans
+pub fn parse(source: &str) -> io::Result<IndexedMesh> {
+    let mut mesh = IndexedMesh::default();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line_number + 1, message),
+            )
+        };
+
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let coordinates = tokens
+                    .take(3)
+                    .map(|token| token.parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|_| error("invalid vertex coordinate"))?;
+
+                if coordinates.len() != 3 {
+                    return Err(error("vertex needs three coordinates"));
+                }
+
+                mesh.vertices
+                    .push(Point3::new(coordinates[0], coordinates[1], coordinates[2]));
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|token| resolve_index(token, mesh.vertices.len()))
+                    .collect::<Option<Vec<usize>>>()
+                    .ok_or_else(|| error("invalid face index"))?;
+
+                if corners.len() < 3 {
+                    return Err(error("face needs at least three corners"));
+                }
+
+                for i in 1..corners.len() - 1 {
+                    mesh.indices.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(mesh)
+}
+
+// face corners look like `v`, `v/vt`, `v//vn` or `v/vt/vn`, where `v` is
+// 1-based, or relative to the end of the vertex list when negative
+fn resolve_index(token: &str, vertex_count: usize) -> Option<usize> {
+    let index = token.split('/').next()?.parse::<isize>().ok()?;
+
+    let resolved = if index < 0 {
+        vertex_count as isize + index
+    } else {
+        index - 1
+    };
+
+    if resolved >= 0 && (resolved as usize) < vertex_count {
+        Some(resolved as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_polygons_into_fans() {
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn reads_texture_and_normal_forms_and_negative_indices() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+                      f 1/1 2//1 3/1/1\nf -3 -2 -1\n";
+        let mesh = parse(source).unwrap();
+
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 1, 2]]);
+    }
+
+    #[test]
+    fn ignores_other_statements() {
+        let mesh = parse("# comment\nmtllib a.mtl\no thing\nv 0 0 0\ns off\n").unwrap();
+
+        assert_eq!(mesh.vertices, vec![Point3::new(0.0, 0.0, 0.0)]);
+        assert!(mesh.indices.is_empty());
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        let out_of_range = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").unwrap_err();
+        assert_eq!(out_of_range.to_string(), "line 4: invalid face index");
+
+        let zero = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n").unwrap_err();
+        assert_eq!(zero.to_string(), "line 4: invalid face index");
+
+        let short = parse("v 0 0\n").unwrap_err();
+        assert_eq!(short.to_string(), "line 1: vertex needs three coordinates");
+
+        let two_corners = parse("v 0 0 0\nv 1 0 0\nf 1 2\n").unwrap_err();
+        assert_eq!(
+            two_corners.to_string(),
+            "line 3: face needs at least three corners"
+        );
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 3 2\nf 1 2 4\n";
+        let mut written = vec![];
+        write(&mut written, &parse(source).unwrap()).unwrap();
+
+        assert_eq!(String::from_utf8(written).unwrap(), source);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::Face;
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub fn cube() -> Vec<Face> {
+    let vertices = [
+        Point3::new(-0.5, -0.5, -0.5),
+        Point3::new(-0.5, 0.5, -0.5),
+        Point3::new(0.5, 0.5, -0.5),
+        Point3::new(0.5, -0.5, -0.5),
+        Point3::new(-0.5, -0.5, 0.5),
+        Point3::new(-0.5, 0.5, 0.5),
+        Point3::new(0.5, 0.5, 0.5),
+        Point3::new(0.5, -0.5, 0.5),
+    ];
+
+    let indices = [
+        [0, 3, 1],
+        [1, 3, 2],
+        [0, 1, 5],
+        [0, 5, 4],
+        [1, 2, 5],
+        [6, 5, 2],
+        [7, 6, 2],
+        [7, 2, 3],
+        [7, 3, 0],
+        [4, 7, 0],
+        [5, 6, 4],
+        [4, 6, 7],
+    ];
+
+    indices
+        .iter()
+        .map(|group| [vertices[group[0]], vertices[group[1]], vertices[group[2]]])
+        .collect()
+}
+
+pub fn octahedron() -> Vec<Face> {
+    let f: f32 = 2.0f32.sqrt() / 2.0;
+    let vertices = [
+        Point3::new(0.0, -1.0, 0.0),
+        Point3::new(-f, 0.0, f),
+        Point3::new(f, 0.0, f),
+        Point3::new(f, 0.0, -f),
+        Point3::new(-f, 0.0, -f),
+        Point3::new(0.0, 1.0, 0.0),
+    ];
+
+    let indices: [[usize; 3]; 8] = [
+        [0, 2, 1],
+        [0, 3, 2],
+        [0, 4, 3],
+        [0, 1, 4],
+        [5, 1, 2],
+        [5, 2, 3],
+        [5, 3, 4],
+        [5, 4, 1],
+    ];
+
+    indices
+        .iter()
+        .map(|group| [vertices[group[0]], vertices[group[1]], vertices[group[2]]])
+        .collect()
+}
+
+pub fn icosahedron() -> Vec<Face> {
+    let vertices = [
+        Point3::new(0.000, 0.000, 1.000),
+        Point3::new(0.894, 0.000, 0.447),
+        Point3::new(0.276, 0.851, 0.447),
+        Point3::new(-0.724, 0.526, 0.447),
+        Point3::new(-0.724, -0.526, 0.447),
+        Point3::new(0.276, -0.851, 0.447),
+        Point3::new(0.724, 0.526, -0.447),
+        Point3::new(-0.276, 0.851, -0.447),
+        Point3::new(-0.894, 0.000, -0.447),
+        Point3::new(-0.276, -0.851, -0.447),
+        Point3::new(0.724, -0.526, -0.447),
+        Point3::new(0.000, 0.000, -1.000),
+    ];
+
+    let indices = [
+        [0, 1, 2],
+        [0, 2, 3],
+        [0, 3, 4],
+        [0, 4, 5],
+        [0, 5, 1],
+        [11, 7, 6],
+        [11, 8, 7],
+        [11, 9, 8],
+        [11, 10, 9],
+        [11, 6, 10],
+        [1, 6, 2],
+        [2, 7, 3],
+        [3, 8, 4],
+        [4, 9, 5],
+        [5, 10, 1],
+        [6, 7, 2],
+        [7, 8, 3],
+        [8, 9, 4],
+        [9, 10, 5],
+        [10, 6, 1],
+    ];
+
+    indices
+        .iter()
+        .map(|group| [vertices[group[0]], vertices[group[1]], vertices[group[2]]])
+        .collect()
+}
+
+// every subdivision quadruples the face count, this is 20480 faces
+pub const MAX_SUBDIVISIONS: usize = 5;
+
+// the icosahedron with each face split into four, `subdivisions` times over,
+// pushed out onto the unit sphere
+pub fn icosphere(subdivisions: usize) -> Vec<Face> {
+    let mut faces = icosahedron();
+
+    for _ in 0..subdivisions {
+        faces = faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = Point3::from((a.coords + b.coords) / 2.0);
+                let bc = Point3::from((b.coords + c.coords) / 2.0);
+                let ca = Point3::from((c.coords + a.coords) / 2.0);
+                vec![[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    faces
+        .iter()
+        .map(|face| {
+            [
+                Point3::from(face[0].coords.normalize()),
+                Point3::from(face[1].coords.normalize()),
+                Point3::from(face[2].coords.normalize()),
+            ]
+        })
+        .collect()
+}
+
+// the most faces the `benchmark` shape of the registry asks for
+pub const MAX_BENCHMARK_FACES: usize = 1_000_000;
+
+// a grid of icospheres with roughly `face_count` faces between them, one mesh
+// per sphere, fitting in the same unit-sized box as the other primitives.
+// the same count always gives the same scene, so timings can be compared
+pub fn benchmark_scene(face_count: usize) -> Vec<Vec<Face>> {
+    // the finest spheres that still leave at least 16 of them, so the depth
+    // sort has plenty of meshes and faces to interleave
+    let mut subdivisions = 0;
+    while subdivisions < MAX_SUBDIVISIONS
+        && 20 * 4usize.pow(subdivisions as u32 + 1) * 16 <= face_count
+    {
+        subdivisions += 1;
+    }
+
+    let sphere = icosphere(subdivisions);
+    let count = ((face_count as f32 / sphere.len() as f32).round() as usize).max(1);
+    let side = (count as f32).sqrt().ceil() as usize;
+    let spacing = 2.0 / side as f32;
+    let radius = spacing * 0.4;
+
+    (0..count)
+        .map(|i| {
+            let offset = Vector3::new(
+                ((i % side) as f32 + 0.5) * spacing - 1.0,
+                ((i / side) as f32 + 0.5) * spacing - 1.0,
+                0.0,
+            );
+
+            sphere
+                .iter()
+                .map(|face| {
+                    [
+                        face[0] * radius + offset,
+                        face[1] * radius + offset,
+                        face[2] * radius + offset,
+                    ]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// `count` evenly spread points on the unit sphere, following a fibonacci
+// lattice: equal-area bands from pole to pole, each point turned by the golden
+// angle from the previous one
+pub fn fibonacci_sphere(count: usize) -> Vec<Point3<f32>> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let radius = (1.0 - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            Point3::new(radius * theta.cos(), y, radius * theta.sin())
+        })
+        .collect()
+}
+
+// the arguments of a shape spec like `my_gear(teeth=12, radius=2.5)`
+#[derive(Clone, Debug, Default)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub fn get<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.0.get(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("invalid value for {}: {}", name, value)),
+            None => Ok(default),
+        }
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+pub type Generator = Box<dyn Fn(&Params) -> Result<Vec<Face>, String> + Send + Sync>;
+
+// named shape generators, available to scene files and the cli as
+// `shape = "name(param=value, ...)"`
+pub struct Registry {
+    generators: HashMap<String, Generator>,
+}
+
+impl Registry {
+    // a registry without any shapes, not even the built-in ones
+    pub fn empty() -> Self {
+        Registry {
+            generators: HashMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, name: &str, generator: F)
+    where
+        F: Fn(&Params) -> Result<Vec<Face>, String> + Send + Sync + 'static,
+    {
+        self.generators
+            .insert(name.to_string(), Box::new(generator));
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self
+            .generators
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn build(&self, spec: &str) -> Result<Vec<Face>, String> {
+        let (name, params) = parse_spec(spec)?;
+
+        match self.generators.get(name) {
+            Some(generator) => generator(&params),
+            None => Err(format!(
+                "unknown shape {} (expected one of: {})",
+                name,
+                self.names().join(", ")
+            )),
+        }
+    }
+}
+
+impl Default for Registry {
+    // the built-in shapes, each taking an optional `scale`
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        registry.register("cube", |params| scaled(cube(), params));
+        registry.register("octahedron", |params| scaled(octahedron(), params));
+        registry.register("icosahedron", |params| scaled(icosahedron(), params));
+        registry.register("icosphere", |params| {
+            let subdivisions = params.get("subdivisions", 2)?;
+            if subdivisions > MAX_SUBDIVISIONS {
+                return Err(format!(
+                    "subdivisions must be at most {}, got {}",
+                    MAX_SUBDIVISIONS, subdivisions
+                ));
+            }
+            scaled(icosphere(subdivisions), params)
+        });
+        // all of the benchmark scene's spheres as a single mesh
+        registry.register("benchmark", |params| {
+            let face_count = params.get("faces", 10_000)?;
+            if face_count > MAX_BENCHMARK_FACES {
+                return Err(format!(
+                    "faces must be at most {}, got {}",
+                    MAX_BENCHMARK_FACES, face_count
+                ));
+            }
+            scaled(benchmark_scene(face_count).concat(), params)
+        });
+        registry
+    }
+}
+
+fn scaled(faces: Vec<Face>, params: &Params) -> Result<Vec<Face>, String> {
+    let scale = params.get("scale", 1.0f32)?;
+
+    Ok(faces
+        .iter()
+        .map(|face| [scale * face[0], scale * face[1], scale * face[2]])
+        .collect())
+}
+
+fn parse_spec(spec: &str) -> Result<(&str, Params), String> {
+    let spec = spec.trim();
+
+    let (name, arguments) = match spec.find('(') {
+        Some(open) if spec.ends_with(')') => (&spec[..open], &spec[open + 1..spec.len() - 1]),
+        Some(_) => return Err(format!("unclosed parameter list: {}", spec)),
+        None => (spec, ""),
+    };
+
+    let mut params = Params::default();
+
+    for argument in arguments.split(',').filter(|a| !a.trim().is_empty()) {
+        let mut pair = argument.splitn(2, '=');
+        match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                params.0.insert(key.trim().to_string(), value.to_string());
+            }
+            _ => return Err(format!("expected name=value, got: {}", argument.trim())),
+        }
+    }
+
+    Ok((name.trim(), params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_count(scene: &[Vec<Face>]) -> usize {
+        scene.iter().map(Vec::len).sum()
+    }
+
+    #[test]
+    fn benchmark_scene_has_about_the_requested_faces() {
+        for &requested in &[1_000, 10_000, 100_000, MAX_BENCHMARK_FACES] {
+            let actual = face_count(&benchmark_scene(requested)) as f32;
+            let requested = requested as f32;
+            assert!(
+                (actual - requested).abs() <= 0.05 * requested,
+                "asked for {} faces, got {}",
+                requested,
+                actual
+            );
+        }
+
+        // always at least one sphere
+        assert_eq!(face_count(&benchmark_scene(0)), 20);
+    }
+
+    #[test]
+    fn benchmark_scene_is_deterministic() {
+        assert_eq!(benchmark_scene(10_000), benchmark_scene(10_000));
+    }
+
+    #[test]
+    fn registry_caps_the_size_of_shapes() {
+        let registry = Registry::default();
+
+        assert!(registry.build("icosphere(subdivisions=5)").is_ok());
+        assert_eq!(
+            registry.build("icosphere(subdivisions=16)").unwrap_err(),
+            "subdivisions must be at most 5, got 16"
+        );
+        assert_eq!(
+            registry.build("benchmark(faces=1000000000)").unwrap_err(),
+            "faces must be at most 1000000, got 1000000000"
+        );
+    }
+}
@@ -0,0 +1,611 @@
+use crate::primitives::Registry;
+use crate::{Camera, CameraError, Engine, Face, Fov, Halo, Mesh, Scene, View};
+use nalgebra::{Point3, Vector3};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// a declarative scene, conventionally in a `.svg3d` file. it looks like toml
+// but isn't (`[mesh]` repeats for every mesh), e.g.
+//
+//     version = 2
+//     width = 640
+//     height = 480
+//
+//     [camera]
+//     from = [13, 2, 20]
+//     fovy = 15
+//
+//     [mesh]
+//     shape = "octahedron(scale=15)"
+//     corner_radius = 0.01
+//
+//     [mesh.style]
+//     fill = "#eee"
+//
+// every `[mesh]` section adds a mesh, configured by `shape`,
+// `corner_radius`, `halo`, `halo_color`, `halo_width`, `min_projected_area`,
+// `clip_to` (the 0-based index of another mesh in the file), `linejoin`,
+// `linecap` and `miter_limit`. a `[mesh.style]` section after it holds the
+// svg style attributes of that mesh. the camera's `fovy` is in degrees unless
+// `fov_unit = "radians"`.
+//
+// files without a `version` are version 1, where `fovy` was in radians and
+// style attributes were given in `[mesh]` itself. they still load as before
+pub struct SceneFile {
+    pub width: u32,
+    pub height: u32,
+    // see Engine::stable_ids
+    pub stable_ids: bool,
+    // see Engine::css_variables
+    pub css_variables: bool,
+    pub camera: Camera,
+    pub meshes: Vec<Mesh<String>>,
+}
+
+impl SceneFile {
+    // a scene of a single shape, framed by the default camera
+    pub fn with_shape(registry: &Registry, spec: &str) -> Result<Self, String> {
+        let mut scene = SceneFile::default();
+        scene.meshes.push(Mesh::new(registry.build(spec)?));
+        Ok(scene)
+    }
+
+    pub fn render(&self, filename: String) -> io::Result<()> {
+        let scene = Scene::new(self.meshes.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut engine = Engine::new(vec![View::new(self.camera.clone(), scene)]);
+        engine.width = self.width;
+        engine.height = self.height;
+        engine.stable_ids = self.stable_ids;
+        engine.css_variables = self.css_variables;
+        engine.render(filename)
+    }
+}
+
+impl Default for SceneFile {
+    fn default() -> Self {
+        SceneFile {
+            width: 512,
+            height: 512,
+            stable_ids: false,
+            css_variables: false,
+            camera: CameraEntry::default()
+                .camera()
+                .expect("the default camera is valid"),
+            meshes: vec![],
+        }
+    }
+}
+
+struct CameraEntry {
+    // 15 degrees when not given
+    fovy: Option<f32>,
+    // whether fovy is in degrees rather than radians
+    degrees: bool,
+    aspect: Option<f32>,
+    near: f32,
+    far: f32,
+    from: Point3<f32>,
+    to: Point3<f32>,
+    up: Vector3<f32>,
+}
+
+impl Default for CameraEntry {
+    fn default() -> Self {
+        CameraEntry {
+            fovy: None,
+            degrees: true,
+            aspect: None,
+            near: 10.0,
+            far: 200.0,
+            from: Point3::new(50.0, 40.0, 120.0),
+            to: Point3::new(0.0, 0.0, 0.0),
+            up: Vector3::y(),
+        }
+    }
+}
+
+impl CameraEntry {
+    fn camera(&self) -> Result<Camera, CameraError> {
+        let fovy = match self.fovy {
+            Some(fovy) if self.degrees => Fov::Degrees(fovy),
+            Some(fovy) => Fov::Radians(fovy),
+            None => Fov::Degrees(15.0),
+        };
+
+        Camera::new(
+            fovy,
+            self.aspect,
+            self.near,
+            self.far,
+            self.from,
+            self.to,
+            self.up,
+        )
+    }
+}
+
+// the schema version written by `upgrade`. loading never changes what an
+// existing file means: files are read by the rules of the version they
+// declare and migrated forward one version at a time
+pub const VERSION: u32 = 2;
+
+// the mesh settings of version 1, where every other `[mesh]` key was a style
+// attribute. frozen: settings added later must not go here, or v1 files that
+// use their name as a style attribute would change meaning
+const V1_MESH_KEYS: &[&str] = &[
+    "shape",
+    "corner_radius",
+    "halo",
+    "halo_color",
+    "halo_width",
+    "min_projected_area",
+    "clip_to",
+    "linejoin",
+    "linecap",
+    "miter_limit",
+];
+
+struct Entry {
+    line: usize,
+    key: String,
+    value: String,
+}
+
+// a `[name]` header and the entries under it. the entries before the first
+// header are in a section without a name
+struct Section {
+    line: usize,
+    name: String,
+    entries: Vec<Entry>,
+}
+
+impl Section {
+    fn new(line: usize, name: &str) -> Self {
+        Section {
+            line,
+            name: name.to_string(),
+            entries: vec![],
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+fn line_error(line: usize, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+pub fn load<P: AsRef<Path>>(path: P, registry: &Registry) -> io::Result<SceneFile> {
+    parse(&fs::read_to_string(path)?, registry)
+}
+
+pub fn parse(source: &str, registry: &Registry) -> io::Result<SceneFile> {
+    build(&migrate(read(source)?)?, registry)
+}
+
+// rewrites a scene file of any supported version as the current version, so
+// that old assets can be brought forward once rather than on every load.
+// comments don't survive the rewrite
+pub fn upgrade(source: &str) -> io::Result<String> {
+    let mut text = String::new();
+
+    for section in migrate(read(source)?)? {
+        if !section.name.is_empty() {
+            text.push_str(&format!("\n[{}]\n", section.name));
+        }
+
+        for entry in section.entries {
+            text.push_str(&format!("{} = {}\n", entry.key, entry.value));
+        }
+    }
+
+    Ok(text.trim_start().to_string())
+}
+
+fn read(source: &str) -> io::Result<Vec<Section>> {
+    let mut sections = vec![Section::new(0, "")];
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line_number = line_number + 1;
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            sections.push(Section::new(line_number, line[1..line.len() - 1].trim()));
+            continue;
+        }
+
+        let mut pair = line.splitn(2, '=');
+        match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) => sections.last_mut().unwrap().entries.push(Entry {
+                line: line_number,
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }),
+            _ => {
+                return Err(line_error(
+                    line_number,
+                    format!("expected key = value, got: {}", line),
+                ))
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+// files without a `version` are version 1, the format from before versioning
+fn migrate(mut sections: Vec<Section>) -> io::Result<Vec<Section>> {
+    let mut version = match sections[0].get("version") {
+        Some(entry) => scalar::<u32>(&entry.value).map_err(|e| line_error(entry.line, e))?,
+        None => 1,
+    };
+
+    if version == 0 || version > VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported scene file version {}, this svg3d reads versions 1 to {}",
+                version, VERSION
+            ),
+        ));
+    }
+
+    while version < VERSION {
+        sections = match version {
+            1 => upgrade_v1(sections)?,
+            _ => unreachable!(),
+        };
+        version += 1;
+    }
+
+    Ok(sections)
+}
+
+// version 2 reads the camera's fovy in degrees unless told otherwise, and
+// keeps mesh style attributes in a `[mesh.style]` section of their own
+// instead of mixing them with the mesh settings
+fn upgrade_v1(sections: Vec<Section>) -> io::Result<Vec<Section>> {
+    let mut upgraded = vec![];
+
+    for mut section in sections {
+        match section.name.as_str() {
+            "" => {
+                section.entries.retain(|entry| entry.key != "version");
+                section.entries.insert(
+                    0,
+                    Entry {
+                        line: 0,
+                        key: "version".to_string(),
+                        value: "2".to_string(),
+                    },
+                );
+                upgraded.push(section);
+            }
+            "camera" => {
+                if let (Some(fovy), None) = (section.get("fovy"), section.get("fov_unit")) {
+                    let line = fovy.line;
+                    section.entries.push(Entry {
+                        line,
+                        key: "fov_unit".to_string(),
+                        value: "\"radians\"".to_string(),
+                    });
+                }
+                upgraded.push(section);
+            }
+            "mesh" => {
+                let (settings, style) = section
+                    .entries
+                    .into_iter()
+                    .partition(|entry| V1_MESH_KEYS.contains(&entry.key.as_str()));
+                let line = section.line;
+                section.entries = settings;
+                upgraded.push(section);
+
+                if !style.is_empty() {
+                    let mut style_section = Section::new(line, "mesh.style");
+                    style_section.entries = style;
+                    upgraded.push(style_section);
+                }
+            }
+            "mesh.style" => {
+                return Err(line_error(
+                    section.line,
+                    "[mesh.style] needs version = 2 or later".to_string(),
+                ))
+            }
+            _ => upgraded.push(section),
+        }
+    }
+
+    Ok(upgraded)
+}
+
+fn build(sections: &[Section], registry: &Registry) -> io::Result<SceneFile> {
+    let mut scene = SceneFile::default();
+    let mut camera = CameraEntry::default();
+    // (line of the section header, shape spec, settings)
+    let mut meshes: Vec<(usize, Option<String>, Mesh<String>)> = vec![];
+
+    for section in sections {
+        if section.name == "mesh" {
+            meshes.push((section.line, None, Mesh::new(Vec::<Face>::new())));
+        }
+
+        for Entry { line, key, value } in &section.entries {
+            let error = |message: String| line_error(*line, message);
+            let (key, value) = (key.as_str(), value.as_str());
+
+            match section.name.as_str() {
+                "" => match key {
+                    "version" => (),
+                    "width" => scene.width = size(value).map_err(error)?,
+                    "height" => scene.height = size(value).map_err(error)?,
+                    "stable_ids" => scene.stable_ids = scalar(value).map_err(error)?,
+                    "css_variables" => scene.css_variables = scalar(value).map_err(error)?,
+                    _ => return Err(error(format!("unknown key {}", key))),
+                },
+                "camera" => match key {
+                    "fovy" => camera.fovy = Some(scalar(value).map_err(error)?),
+                    "fov_unit" => {
+                        camera.degrees = match string(value) {
+                            "degrees" => true,
+                            "radians" => false,
+                            other => return Err(error(format!("unknown fov_unit {}", other))),
+                        }
+                    }
+                    "aspect" => camera.aspect = Some(scalar(value).map_err(error)?),
+                    "near" => camera.near = scalar(value).map_err(error)?,
+                    "far" => camera.far = scalar(value).map_err(error)?,
+                    "from" => camera.from = Point3::from(vector(value).map_err(error)?),
+                    "to" => camera.to = Point3::from(vector(value).map_err(error)?),
+                    "up" => camera.up = vector(value).map_err(error)?,
+                    _ => return Err(error(format!("unknown camera key {}", key))),
+                },
+                "mesh" => {
+                    let (_, shape, mesh) = meshes.last_mut().unwrap();
+                    match key {
+                        "shape" => *shape = Some(string(value).to_string()),
+                        "corner_radius" => mesh.corner_radius = scalar(value).map_err(error)?,
+                        "halo" => {
+                            mesh.halo = if scalar::<bool>(value).map_err(error)? {
+                                Some(mesh.halo.take().unwrap_or_default())
+                            } else {
+                                None
+                            }
+                        }
+                        "min_projected_area" => {
+                            mesh.min_projected_area = scalar(value).map_err(error)?
+                        }
+                        "clip_to" => mesh.clip_to = Some(scalar(value).map_err(error)?),
+                        "linejoin" => mesh.linejoin = scalar(value).map_err(error)?,
+                        "linecap" => mesh.linecap = Some(scalar(value).map_err(error)?),
+                        "miter_limit" => {
+                            mesh.miter_limit = Some(miter_limit(value).map_err(error)?)
+                        }
+                        "halo_color" => {
+                            mesh.halo.get_or_insert_with(Halo::default).color =
+                                string(value).to_string()
+                        }
+                        "halo_width" => {
+                            mesh.halo.get_or_insert_with(Halo::default).width =
+                                scalar(value).map_err(error)?
+                        }
+                        // unlike version 1, style attributes go in [mesh.style]
+                        _ => return Err(error(format!("unknown mesh key {}", key))),
+                    }
+                }
+                "mesh.style" => match meshes.last_mut() {
+                    Some((_, _, mesh)) => {
+                        mesh.style
+                            .insert(key.to_string(), string(value).to_string());
+                    }
+                    None => return Err(error("[mesh.style] before any [mesh]".to_string())),
+                },
+                other => {
+                    return Err(line_error(
+                        section.line,
+                        format!("unknown section [{}]", other),
+                    ))
+                }
+            }
+        }
+    }
+
+    scene.camera = camera
+        .camera()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("[camera]: {}", e)))?;
+
+    let mesh_count = meshes.len();
+
+    for (line_number, shape, mut mesh) in meshes {
+        let error = |message: String| line_error(line_number, message);
+
+        let spec = shape.ok_or_else(|| error("mesh without a shape".to_string()))?;
+        mesh.faces = registry.build(&spec).map_err(error)?.into();
+
+        if let Some(clip_index) = mesh.clip_to {
+            if clip_index >= mesh_count {
+                return Err(error(format!(
+                    "clip_to {} is out of range, there are {} meshes",
+                    clip_index, mesh_count
+                )));
+            }
+        }
+
+        scene.meshes.push(mesh);
+    }
+
+    Ok(scene)
+}
+
+fn string(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn scalar<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    string(value)
+        .parse()
+        .map_err(|_| format!("invalid value: {}", value))
+}
+
+fn size(value: &str) -> Result<u32, String> {
+    match scalar(value)? {
+        0 => Err("document size must be positive".to_string()),
+        size => Ok(size),
+    }
+}
+
+fn miter_limit(value: &str) -> Result<f32, String> {
+    match scalar::<f32>(value)? {
+        limit if limit >= 1.0 && limit.is_finite() => Ok(limit),
+        limit => Err(format!("miter_limit must be at least 1, got {}", limit)),
+    }
+}
+
+fn vector(value: &str) -> Result<Vector3<f32>, String> {
+    let components = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| format!("expected [x, y, z], got: {}", value))?
+        .split(',')
+        .map(scalar::<f32>)
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    match components.as_slice() {
+        [x, y, z] => Ok(Vector3::new(*x, *y, *z)),
+        _ => Err(format!("expected [x, y, z], got: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(source: &str) -> io::Result<SceneFile> {
+        parse(source, &Registry::default())
+    }
+
+    fn error(source: &str) -> String {
+        match load(source) {
+            Ok(_) => panic!("expected an error for {:?}", source),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn v1_fovy_is_in_radians() {
+        let v1 = load("[camera]\nfovy = 0.3\n").unwrap();
+        assert_eq!(v1.camera.fovy, 0.3);
+
+        let v2 = load("version = 2\n[camera]\nfovy = 15\n").unwrap();
+        assert_eq!(v2.camera.fovy, 15f32.to_radians());
+
+        let v2_radians = load("version = 2\n[camera]\nfovy = 0.3\nfov_unit = \"radians\"\n");
+        assert_eq!(v2_radians.unwrap().camera.fovy, 0.3);
+    }
+
+    #[test]
+    fn default_fovy_is_the_same_in_every_version() {
+        let v1 = load("").unwrap();
+        let v2 = load("version = 2\n").unwrap();
+        assert_eq!(v1.camera.fovy, 15f32.to_radians());
+        assert_eq!(v2.camera.fovy, v1.camera.fovy);
+    }
+
+    #[test]
+    fn v1_mesh_keys_split_into_settings_and_style() {
+        let v1 = load("[mesh]\nshape = \"cube\"\ncorner_radius = 0.01\nfill = \"#eee\"\n").unwrap();
+        let v2 = load(
+            "version = 2\n[mesh]\nshape = \"cube\"\ncorner_radius = 0.01\n\
+             [mesh.style]\nfill = \"#eee\"\n",
+        )
+        .unwrap();
+
+        for scene in &[v1, v2] {
+            assert_eq!(scene.meshes.len(), 1);
+            assert_eq!(scene.meshes[0].corner_radius, 0.01);
+            assert_eq!(scene.meshes[0].style.len(), 1);
+            assert_eq!(scene.meshes[0].style["fill"], "#eee");
+        }
+    }
+
+    #[test]
+    fn style_keys_belong_in_their_own_section_from_v2() {
+        assert_eq!(
+            error("version = 2\n[mesh]\nshape = \"cube\"\nfill = \"#eee\"\n"),
+            "line 4: unknown mesh key fill"
+        );
+        assert_eq!(
+            error("[mesh]\nshape = \"cube\"\n[mesh.style]\nfill = \"#eee\"\n"),
+            "line 3: [mesh.style] needs version = 2 or later"
+        );
+        assert_eq!(
+            error("version = 2\n[mesh.style]\nfill = \"#eee\"\n"),
+            "line 3: [mesh.style] before any [mesh]"
+        );
+    }
+
+    #[test]
+    fn rejects_miter_limits_below_one() {
+        assert_eq!(
+            error("version = 2\n[mesh]\nshape = \"cube\"\nmiter_limit = -3\n"),
+            "line 4: miter_limit must be at least 1, got -3"
+        );
+
+        let scene = load("version = 2\n[mesh]\nshape = \"cube\"\nmiter_limit = 1\n").unwrap();
+        assert_eq!(scene.meshes[0].miter_limit, Some(1.0));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert_eq!(
+            error("version = 3\n"),
+            "unsupported scene file version 3, this svg3d reads versions 1 to 2"
+        );
+        assert_eq!(
+            error("version = 0\n"),
+            "unsupported scene file version 0, this svg3d reads versions 1 to 2"
+        );
+        assert_eq!(error("version = two\n"), "line 1: invalid value: two");
+    }
+
+    #[test]
+    fn upgrade_rewrites_v1_as_the_current_version() {
+        let v1 = "# a comment\nwidth = 640\n\n[camera]\nfovy = 0.3\n\n\
+                  [mesh]\nshape = \"cube\"\nfill = \"#eee\"\nhalo = true\n";
+
+        let upgraded = upgrade(v1).unwrap();
+        assert_eq!(
+            upgraded,
+            "version = 2\nwidth = 640\n\n[camera]\nfovy = 0.3\nfov_unit = \"radians\"\n\n\
+             [mesh]\nshape = \"cube\"\nhalo = true\n\n[mesh.style]\nfill = \"#eee\"\n"
+        );
+
+        // already current, so nothing changes
+        assert_eq!(upgrade(&upgraded).unwrap(), upgraded);
+
+        let (before, after) = (load(v1).unwrap(), load(&upgraded).unwrap());
+        assert_eq!(after.width, before.width);
+        assert_eq!(after.camera.fovy, before.camera.fovy);
+        assert_eq!(after.meshes[0].style, before.meshes[0].style);
+        assert_eq!(
+            after.meshes[0].halo.is_some(),
+            before.meshes[0].halo.is_some()
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::IndexedMesh;
+use nalgebra::Point3;
+use std::collections::HashMap;
+use std::fmt;
+
+pub struct Stats {
+    pub vertex_count: usize,
+    pub face_count: usize,
+    // None for a mesh without vertices
+    pub bounds: Option<(Point3<f32>, Point3<f32>)>,
+    // groups of faces connected through shared vertices
+    pub components: usize,
+    // edges used by a single face
+    pub boundary_edges: usize,
+    // edges shared by more than two faces
+    pub non_manifold_edges: usize,
+    // edges shared by two faces that traverse it in the same direction
+    pub inconsistent_edges: usize,
+    // faces that use the same vertex more than once
+    pub degenerate_faces: usize,
+}
+
+impl Stats {
+    pub fn new(mesh: &IndexedMesh) -> Self {
+        let bounds = mesh.vertices.split_first().map(|(first, rest)| {
+            rest.iter().fold((*first, *first), |(min, max), point| {
+                (
+                    Point3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+                    Point3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+                )
+            })
+        });
+
+        // edge (low, high) -> (faces using it, faces traversing it low to high)
+        let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut degenerate_faces = 0;
+
+        for [a, b, c] in &mesh.indices {
+            if a == b || b == c || c == a {
+                degenerate_faces += 1;
+                continue;
+            }
+
+            for &(from, to) in &[(*a, *b), (*b, *c), (*c, *a)] {
+                let entry = edges.entry((from.min(to), from.max(to))).or_insert((0, 0));
+                entry.0 += 1;
+                if from < to {
+                    entry.1 += 1;
+                }
+            }
+        }
+
+        let count_edges = |predicate: &dyn Fn(usize, usize) -> bool| {
+            edges
+                .values()
+                .filter(|(uses, forward)| predicate(*uses, *forward))
+                .count()
+        };
+
+        Stats {
+            vertex_count: mesh.vertices.len(),
+            face_count: mesh.indices.len(),
+            bounds,
+            components: components(mesh),
+            boundary_edges: count_edges(&|uses, _| uses == 1),
+            non_manifold_edges: count_edges(&|uses, _| uses > 2),
+            inconsistent_edges: count_edges(&|uses, forward| uses == 2 && forward != 1),
+            degenerate_faces,
+        }
+    }
+
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges == 0 && self.inconsistent_edges == 0 && self.degenerate_faces == 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.boundary_edges == 0
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "vertices: {}", self.vertex_count)?;
+        writeln!(f, "faces: {}", self.face_count)?;
+        match self.bounds {
+            Some((min, max)) => writeln!(
+                f,
+                "bounds: ({}, {}, {}) to ({}, {}, {})",
+                min.x, min.y, min.z, max.x, max.y, max.z
+            )?,
+            None => writeln!(f, "bounds: empty")?,
+        }
+        writeln!(f, "components: {}", self.components)?;
+        writeln!(f, "boundary edges: {}", self.boundary_edges)?;
+        writeln!(f, "non-manifold edges: {}", self.non_manifold_edges)?;
+        writeln!(
+            f,
+            "inconsistently oriented edges: {}",
+            self.inconsistent_edges
+        )?;
+        writeln!(f, "degenerate faces: {}", self.degenerate_faces)?;
+        write!(
+            f,
+            "manifold: {}{}",
+            if self.is_manifold() { "yes" } else { "no" },
+            if self.is_closed() {
+                ", closed"
+            } else {
+                ", open"
+            }
+        )
+    }
+}
+
+fn components(mesh: &IndexedMesh) -> usize {
+    let mut parents: Vec<usize> = (0..mesh.vertices.len()).collect();
+
+    fn root(parents: &mut [usize], mut vertex: usize) -> usize {
+        while parents[vertex] != vertex {
+            parents[vertex] = parents[parents[vertex]];
+            vertex = parents[vertex];
+        }
+        vertex
+    }
+
+    for [a, b, c] in &mesh.indices {
+        for &other in &[*b, *c] {
+            let (root_a, root_other) = (root(&mut parents, *a), root(&mut parents, other));
+            parents[root_other] = root_a;
+        }
+    }
+
+    let mut roots = mesh
+        .indices
+        .iter()
+        .map(|[a, _, _]| root(&mut parents, *a))
+        .collect::<Vec<usize>>();
+
+    roots.sort_unstable();
+    roots.dedup();
+    roots.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{cube, icosphere};
+
+    #[test]
+    fn closed_meshes_are_manifold() {
+        for faces in &[cube(), icosphere(2)] {
+            let stats = Stats::new(&IndexedMesh::from_faces(faces, 0.0));
+
+            assert_eq!(stats.components, 1);
+            assert_eq!(stats.boundary_edges, 0);
+            assert_eq!(stats.non_manifold_edges, 0);
+            assert_eq!(stats.inconsistent_edges, 0);
+            assert!(stats.is_manifold() && stats.is_closed());
+        }
+    }
+
+    #[test]
+    fn counts_vertices_faces_and_bounds() {
+        let stats = Stats::new(&IndexedMesh::from_faces(&cube(), 0.0));
+
+        assert_eq!(stats.vertex_count, 8);
+        assert_eq!(stats.face_count, 12);
+        assert_eq!(
+            stats.bounds,
+            Some((Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5)))
+        );
+        assert_eq!(Stats::new(&IndexedMesh::default()).bounds, None);
+    }
+
+    #[test]
+    fn finds_open_and_disconnected_parts() {
+        let mesh = IndexedMesh {
+            vertices: vec![Point3::origin(); 6],
+            indices: vec![[0, 1, 2], [3, 4, 5]],
+        };
+        let stats = Stats::new(&mesh);
+
+        assert_eq!(stats.components, 2);
+        assert_eq!(stats.boundary_edges, 6);
+        assert!(stats.is_manifold() && !stats.is_closed());
+    }
+
+    #[test]
+    fn finds_problem_edges_and_faces() {
+        let mesh = IndexedMesh {
+            vertices: vec![Point3::origin(); 6],
+            indices: vec![
+                // three faces on the edge 0-1
+                [0, 1, 2],
+                [1, 0, 3],
+                [1, 0, 4],
+                // traverses 1-2 in the same direction as the first face
+                [1, 2, 5],
+                [2, 2, 3],
+            ],
+        };
+        let stats = Stats::new(&mesh);
+
+        assert_eq!(stats.non_manifold_edges, 1);
+        assert_eq!(stats.inconsistent_edges, 1);
+        assert_eq!(stats.degenerate_faces, 1);
+        assert!(!stats.is_manifold());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::IndexedMesh;
+use nalgebra::Vector3;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub fn save<P: AsRef<Path>>(path: P, mesh: &IndexedMesh) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, mesh)?;
+    writer.flush()
+}
+
+// writes ascii stl, with facet normals following each face's winding
+pub fn write<W: Write>(writer: &mut W, mesh: &IndexedMesh) -> io::Result<()> {
+    writeln!(writer, "solid svg3d")?;
+
+    for [a, b, c] in mesh.faces() {
+        let normal = (b - a)
+            .cross(&(c - a))
+            .try_normalize(0.0)
+            .unwrap_or_else(Vector3::zeros);
+
+        writeln!(
+            writer,
+            "  facet normal {} {} {}",
+            normal.x, normal.y, normal.z
+        )?;
+        writeln!(writer, "    outer loop")?;
+        for vertex in &[a, b, c] {
+            writeln!(
+                writer,
+                "      vertex {} {} {}",
+                vertex.x, vertex.y, vertex.z
+            )?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+
+    writeln!(writer, "endsolid svg3d")
+}